@@ -0,0 +1,106 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Failure detection for `--non-interactive`, which needs to exit non-zero on
+//! the first failed command even though `cli::commands::Command::execute`
+//! reports its own errors via `report_error` and returns `()` -- there's no
+//! `Result` for `run_non_interactive` to inspect directly.
+//!
+//! Two layers cover this:
+//! - Call sites this crate owns (wallet recovery, `validator-manager`) call
+//!   [`report`] instead of `cli::commands::report_error` directly, which both
+//!   prints the error the same way and raises a flag [`take`] can observe.
+//! - Commands dispatched through `cli::commands::get_commands`'s registry
+//!   (`transfer`, `account create`, ...) call the real `report_error`
+//!   directly, so that flag never sees them fail. [`execute_detecting_failure`]
+//!   covers these too by capturing stderr around the call: `report_error`
+//!   prints to stderr, so any output there during a command's `execute` is
+//!   treated as a failure, the same signal an operator watching the terminal
+//!   would use.
+
+use cli::client_proxy::ClientProxy;
+use cli::commands::{report_error, Command};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static LAST_COMMAND_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Reports `error` the same way `cli::commands::report_error` does, and
+/// marks the current command as failed for [`take`].
+pub fn report(context: &str, error: anyhow::Error) {
+    report_error(context, error);
+    LAST_COMMAND_FAILED.store(true, Ordering::SeqCst);
+}
+
+/// Clears the flag so it reflects only what happens after this call.
+pub fn reset() {
+    LAST_COMMAND_FAILED.store(false, Ordering::SeqCst);
+}
+
+/// Returns whether [`report`] was called since the last [`reset`].
+pub fn take() -> bool {
+    LAST_COMMAND_FAILED.load(Ordering::SeqCst)
+}
+
+/// Runs `cmd.execute(..)` and returns whether it failed, covering both:
+/// - errors reported through [`report`] (this crate's own commands), and
+/// - errors `cmd` reports directly through `cli::commands::report_error`
+///   (every command from `get_commands`'s registry), detected by capturing
+///   stderr for the duration of the call and treating any output there as a
+///   failure.
+///
+/// If stderr can't be captured (not a real fd, e.g. under some test
+/// harnesses), falls back to only the former.
+pub fn execute_detecting_failure(cmd: &dyn Command, client_proxy: &mut ClientProxy, params: &[&str]) -> bool {
+    reset();
+    match gag::BufferRedirect::stderr() {
+        Ok(mut captured) => {
+            cmd.execute(client_proxy, params);
+            let mut stderr_output = String::new();
+            captured
+                .read_to_string(&mut stderr_output)
+                .expect("captured stderr is valid UTF-8");
+            // Restore stderr and pass the captured output through, so the
+            // error text is still shown to the operator.
+            drop(captured);
+            eprint!("{}", stderr_output);
+            take() || !stderr_output.trim().is_empty()
+        }
+        Err(_) => {
+            cmd.execute(client_proxy, params);
+            take()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{report, reset, take};
+    use std::sync::Mutex;
+
+    // `LAST_COMMAND_FAILED` is global, so tests that touch it must not run concurrently.
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn take_is_false_until_report_is_called() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        reset();
+        assert!(!take());
+    }
+
+    #[test]
+    fn report_sets_the_flag_that_take_observes() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        reset();
+        report("test context", anyhow::anyhow!("boom"));
+        assert!(take());
+    }
+
+    #[test]
+    fn reset_clears_a_previously_reported_failure() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        report("test context", anyhow::anyhow!("boom"));
+        reset();
+        assert!(!take());
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Support for deriving accounts from a Ledger hardware wallet instead of an
+//! in-process mnemonic, so a mnemonic never enters this process during a
+//! `--ledger` session. [`LedgerSigner::sign_transaction`] exists and sends a
+//! transaction to the device for on-screen confirmation and signing, but it
+//! is not yet called from anywhere: `main` only uses this module for account
+//! recovery (`recover_accounts_from_ledger`), and every actual transaction is
+//! still signed by `ClientProxy`'s normal in-process signer. Wiring real
+//! commands through the device would mean threading a signer through
+//! `cli::client_proxy::ClientProxy`, which lives in a separate, upstream
+//! crate this change doesn't touch.
+
+use anyhow::{format_err, Result};
+use cli::client_proxy::AccountData;
+use ledger_transport_hid::{hidapi::HidApi, TransportNativeHID};
+use libra_crypto::ed25519::Ed25519PublicKey;
+use libra_types::account_address::AccountAddress;
+use std::convert::TryFrom;
+
+/// Libra's BIP44 coin type, used to build the derivation path sent to the device.
+const LIBRA_COIN_TYPE: u32 = 6565;
+
+/// Number of child accounts derived from a Ledger device on startup, matching
+/// the default used by `ClientProxy::recover_accounts_in_wallet`.
+pub const DEFAULT_WALLET_RECOVERY_SIZE: u32 = 10;
+
+/// An open connection to a Ledger device, scoped to Libra's app on the device.
+pub struct LedgerSigner {
+    transport: TransportNativeHID,
+}
+
+impl LedgerSigner {
+    /// Opens a transport to the first Ledger device found over USB/HID.
+    ///
+    /// Returns an error rather than blocking if no device is attached; callers
+    /// should surface this the same way they surface a missing mnemonic file.
+    pub fn connect() -> Result<Self> {
+        let api = HidApi::new().map_err(|e| format_err!("Failed to initialize HID API: {}", e))?;
+        let transport = TransportNativeHID::new(&api)
+            .map_err(|e| format_err!("Failed to open Ledger device: {}", e))?;
+        Ok(Self { transport })
+    }
+
+    /// Derivation path for the `index`-th Libra account: `44'/6565'/index'/0'/0'`.
+    fn derivation_path(index: u32) -> Vec<u32> {
+        vec![
+            44 | 0x8000_0000,
+            LIBRA_COIN_TYPE | 0x8000_0000,
+            index | 0x8000_0000,
+            0x8000_0000,
+            0x8000_0000,
+        ]
+    }
+
+    /// Asks the device for the public key at `index` without any on-screen
+    /// confirmation. Used only to enumerate addresses during recovery; no
+    /// signature is produced and no private key ever leaves the device.
+    pub fn get_public_key(&self, index: u32) -> Result<Ed25519PublicKey> {
+        let path = Self::derivation_path(index);
+        let response = self
+            .transport
+            .get_public_key(&path, false /* display_on_device */)
+            .map_err(|e| format_err!("Ledger denied public key request for index {}: {}", index, e))?;
+        Ed25519PublicKey::try_from(response.public_key.as_slice())
+            .map_err(|e| format_err!("Ledger returned an invalid public key: {}", e))
+    }
+
+    /// Sends `raw_txn_bytes` (a BCS-serialized `RawTransaction`) to the device for
+    /// review and signing. The device displays the transaction summary and the
+    /// holder must physically confirm before a signature is returned.
+    ///
+    /// Not yet reachable from any command: routing transfers and other
+    /// signing commands through this would mean threading a signer through
+    /// `cli::client_proxy::ClientProxy`, which lives in a separate, upstream
+    /// crate not touched by this change. `--ledger` currently covers account
+    /// recovery (public keys only) via [`recover_accounts_from_ledger`]; full
+    /// signing support needs that upstream crate to grow a signer extension
+    /// point first.
+    pub fn sign_transaction(&self, index: u32, raw_txn_bytes: &[u8]) -> Result<Vec<u8>> {
+        let path = Self::derivation_path(index);
+        self.transport
+            .sign_transaction(&path, raw_txn_bytes)
+            .map_err(|e| format_err!("Ledger signing rejected or failed: {}", e))
+    }
+}
+
+/// Hardware-backed analogue of `ClientProxy::recover_accounts_in_wallet`: derives
+/// public keys for the first `count` BIP44 indices from the device and populates
+/// `account_data` without ever reading or holding a mnemonic or private key.
+pub fn recover_accounts_from_ledger(
+    signer: &LedgerSigner,
+    count: u32,
+) -> Result<Vec<AccountData>> {
+    let mut account_data = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let public_key = signer.get_public_key(index)?;
+        let address = AccountAddress::from_public_key(&public_key);
+        account_data.push(AccountData {
+            index,
+            address,
+            authentication_key: None,
+            key_pair: None,
+            sequence_number: 0,
+            status: cli::client_proxy::AccountStatus::Unknown,
+        });
+    }
+    Ok(account_data)
+}
@@ -0,0 +1,59 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Rotating file logging for `--log-file`. `libra_logger::Logger` has no
+//! file-output or rotation support of its own to extend, so this is
+//! implemented with the `flexi_logger` crate directly and installed as the
+//! process's global logger in place of `libra_logger::Logger::init`.
+
+use flexi_logger::{Cleanup, Criterion, FileSpec, Logger as FlexiLogger, Naming, WriteMode};
+use log::LevelFilter;
+use std::path::Path;
+
+/// Rotate once a log file reaches this size...
+const ROTATE_SIZE_MB: u64 = 10;
+/// ...and keep this many rotated-out archives around.
+const KEEP_ARCHIVES: usize = 5;
+
+/// Starts logging at `level` to `log_file`, rotating by size and keeping a
+/// bounded number of archives. Becomes the process's global logger.
+pub fn init_file_logging(log_file: &str, level: LevelFilter) -> Result<(), flexi_logger::FlexiLoggerError> {
+    let path = Path::new(log_file);
+    let directory = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let basename = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("libra-client");
+
+    let mut spec = FileSpec::default().basename(basename);
+    if let Some(directory) = directory {
+        spec = spec.directory(directory);
+    }
+
+    FlexiLogger::try_with_str(level.to_string())?
+        .log_to_file(spec)
+        .rotate(
+            Criterion::Size(ROTATE_SIZE_MB * 1024 * 1024),
+            Naming::Timestamps,
+            Cleanup::KeepLogFiles(KEEP_ARCHIVES),
+        )
+        .write_mode(WriteMode::BufferAndFlush)
+        .start()?;
+    Ok(())
+}
+
+/// Installs `crash_handler`'s panic hook, then wraps it so a panic is also
+/// logged through whatever global logger is active (the rotating file
+/// logger, when `--log-file` is set) instead of only reaching stderr -- a
+/// crash during a long-running session shouldn't disappear from the log just
+/// because it never went through `log::error!`. Both steps happen here, back
+/// to back, so nothing can install its own panic hook in between and get
+/// silently wrapped instead of `crash_handler`'s.
+pub fn init_panic_logging() {
+    crash_handler::setup_panic_handler();
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("{}", info);
+        previous_hook(info);
+    }));
+}
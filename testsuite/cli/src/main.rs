@@ -9,13 +9,28 @@ use chrono::{
 };
 use cli::{
     client_proxy::ClientProxy,
-    commands::{get_commands, parse_cmd, report_error, Command},
+    commands::{get_commands, parse_cmd, Command},
 };
 use libra_types::{chain_id::ChainId, waypoint::Waypoint};
 use rustyline::{config::CompletionType, error::ReadlineError, Config, Editor};
-use std::{env, str::FromStr, time::{Duration, UNIX_EPOCH}};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    env,
+    sync::Arc,
+    time::{Duration, UNIX_EPOCH},
+};
 use structopt::StructOpt;
 
+mod failure;
+mod ledger;
+mod logging;
+mod validator_manager;
+mod wallet_lock;
+mod wallet_setup;
+mod waypoint;
+use ledger::LedgerSigner;
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "Libra Client",
@@ -62,55 +77,175 @@ struct Args {
     #[structopt(
         name = "waypoint",
         long,
-        help = "Explicitly specify the waypoint to use",
-        required_unless = "waypoint_url"
+        help = "Explicitly specify the waypoint to use"
     )]
     pub waypoint: Option<Waypoint>,
-    #[structopt(
-        name = "waypoint_url",
-        long,
-        help = "URL for a file with the waypoint to use",
-        required_unless = "waypoint"
-    )]
-    pub waypoint_url: Option<String>,
+    /// URL for a file with the waypoint to use. May be passed multiple times;
+    /// when more than one is given, the client fetches all of them and only
+    /// proceeds if a strict majority agree on the same waypoint. If neither
+    /// this nor `--waypoint` is passed, falls back to a built-in default URL
+    /// for the selected chain id; only mainnet, testnet and devnet have one.
+    #[structopt(name = "waypoint_url", long, number_of_values = 1)]
+    pub waypoint_url: Vec<String>,
     /// Verbose output.
     #[structopt(short = "v", long = "verbose")]
     pub verbose: bool,
+    /// Derive accounts from a connected Ledger hardware wallet instead of a
+    /// mnemonic read from the TTY, so a mnemonic is never held in process
+    /// memory. Signing is NOT yet routed through the device: every
+    /// transaction is still signed by `ClientProxy`'s normal in-process
+    /// signer. See `LedgerSigner::sign_transaction`.
+    #[structopt(long = "ledger")]
+    pub ledger: bool,
+    /// Run without the interactive REPL: read the mnemonic from the
+    /// `LIBRA_MNEMONIC` environment variable (falling back to an existing
+    /// `--mnemonic-file`), execute the commands from `--commands-file` and/or
+    /// `--exec`, print one JSON result object per command, and exit non-zero
+    /// on the first unrecognized or failed command. Also suppresses the
+    /// human-readable account-recovery banner so JSON is the only output.
+    #[structopt(long = "non-interactive")]
+    pub non_interactive: bool,
+    /// Path to a file of newline-separated commands to run in `--non-interactive`
+    /// mode, in the same syntax accepted by the interactive prompt.
+    #[structopt(long = "commands-file")]
+    pub commands_file: Option<String>,
+    /// A single command to run in `--non-interactive` mode. May be passed more
+    /// than once to run several commands in order.
+    #[structopt(long = "exec", number_of_values = 1)]
+    pub exec: Vec<String>,
+    /// Skip the exclusive lock normally held on the wallet/mnemonic file for
+    /// the lifetime of the session. Only safe for read-only or scripted uses
+    /// that are not themselves racing another instance on the same file.
+    #[structopt(long = "no-wallet-lock")]
+    pub no_wallet_lock: bool,
+    /// Write logs to this file, rotating it once it reaches 10 MB and keeping
+    /// the last 5 archives, instead of only printing warnings to the console.
+    /// When set, console output is limited to command results and full
+    /// diagnostic traces (including panics) go to this file.
+    #[structopt(long = "log-file")]
+    pub log_file: Option<String>,
+    /// Minimum severity to log: "error", "warn", "info", "debug", or "trace".
+    /// Overrides `--verbose` when given.
+    #[structopt(long = "log-level")]
+    pub log_level: Option<String>,
 }
 
 fn main() {
     let args = Args::from_args();
+    let mnemonic_path = wallet_setup::resolve_mnemonic_path(args.mnemonic_file.as_deref());
 
     // TODO: Duplicated with 0L miner.
 
     let mut entered_mnem = false;
-    println!("Enter your 0L mnemonic:");
-    let mnemonic_string = match env::var("NODE_ENV") {
-        Ok(val) => {
-           match val.as_str() {
-            "prod" => rpassword::read_password_from_tty(Some("\u{1F511}")).unwrap(),
-            // for test and stage environments, so mnemonics can be inputted.
-             _ => {
-               println!("(unsafe STDIN input for testing) \u{1F511}");
-               rpassword::read_password().unwrap()
-             }
-           }          
-        },
-        // if not set assume prod
-        _ => rpassword::read_password_from_tty(Some("\u{1F511}")).unwrap()
+    let mut just_generated = false;
+    let mut mnemonic_file_encrypted = false;
+    let mut ledger_signer: Option<LedgerSigner> = None;
+    let mnemonic_string = if args.ledger {
+        // A Ledger device derives and signs on our behalf; never prompt for or
+        // hold a mnemonic in process memory.
+        ledger_signer = Some(LedgerSigner::connect().unwrap_or_else(|e| {
+            panic!("Failed to connect to Ledger device: {}", e)
+        }));
+        println!(
+            "Connected to Ledger device for account recovery. Transaction signing is \
+             NOT yet hardware-backed: every transaction in this session still goes \
+             through ClientProxy's existing in-process signer."
+        );
+        "".to_string()
+    } else if args.non_interactive {
+        // Scripted runs can't read a TTY; generalize the NODE_ENV test/stage
+        // branch in `wallet_setup::prompt_mnemonic` into an explicit,
+        // scriptable source for the mnemonic.
+        // `LIBRA_MNEMONIC` takes priority since it never touches disk; a
+        // pre-existing `--mnemonic-file` is the fallback for scripts that
+        // would rather point at a file than put the mnemonic in env.
+        env::var("LIBRA_MNEMONIC").ok().unwrap_or_else(|| {
+            std::fs::read_to_string(&mnemonic_path)
+                .map(|contents| contents.trim().to_string())
+                .unwrap_or_else(|_| {
+                    panic!(
+                        "--non-interactive requires either the LIBRA_MNEMONIC environment \
+                         variable, an existing --mnemonic-file, or --ledger to supply the \
+                         mnemonic non-interactively"
+                    )
+                })
+        })
+    } else {
+        let entry = wallet_setup::enter_or_generate_mnemonic(Some(&mnemonic_path))
+            .unwrap_or_else(|e| panic!("Failed to set up wallet: {}", e));
+        just_generated = entry.just_generated;
+        mnemonic_file_encrypted = entry.mnemonic_file_encrypted;
+        entry.mnemonic
     };
 
     if mnemonic_string.len() > 0 { entered_mnem = true };
 
+    // Hold an exclusive lock on the wallet/mnemonic file for the lifetime of
+    // the session so a second instance can't interleave writes with this one.
+    // Acquired only now, after `wallet_setup::enter_or_generate_mnemonic` has
+    // already decided (and acted on) whether `mnemonic_path` is a brand new
+    // wallet or an existing one to recover -- opening it any earlier would
+    // create the file first and make that decision always see "recover",
+    // since `wallet_lock::open` has to pass `create(true)` to be able to lock
+    // a file that doesn't exist yet. `mut wallet_lock_file` must outlive
+    // `_wallet_lock_guard`, so both are bound here and dropped together at
+    // the end of `main`.
+    let mut wallet_lock_file = if args.ledger || args.no_wallet_lock {
+        None
+    } else {
+        Some(wallet_lock::open(&mnemonic_path).unwrap_or_else(|e| panic!("{}", e)))
+    };
+    let _wallet_lock_guard = wallet_lock_file.as_mut().map(|lock| {
+        lock.try_lock()
+            .unwrap_or_else(|_| panic!("{}", wallet_lock::conflict_message(&mnemonic_path)))
+    });
 
-    let mut logger = ::libra_logger::Logger::new();
-    if !args.verbose {
-        logger.level(::libra_logger::Level::Warn);
+    // Derive accounts from the device up front; this is the only use of the
+    // signer handle, which is not passed on to `ClientProxy`.
+    let ledger_account_data = ledger_signer.as_ref().map(|signer| {
+        ledger::recover_accounts_from_ledger(signer, ledger::DEFAULT_WALLET_RECOVERY_SIZE)
+            .unwrap_or_else(|e| panic!("Failed to derive accounts from Ledger device: {}", e))
+    });
+
+    match &args.log_file {
+        Some(log_file) => {
+            // `libra_logger::Logger` has no file/rotation support to extend,
+            // so `--log-file` is handled entirely in-crate with `flexi_logger`
+            // instead, which becomes the process's global logger in its
+            // place. Panics are captured into the same file by
+            // `logging::init_panic_logging`, which installs `crash_handler`'s
+            // hook and wraps it in one step.
+            let level = args.log_level.as_deref().map_or(
+                if args.verbose {
+                    log::LevelFilter::Debug
+                } else {
+                    log::LevelFilter::Warn
+                },
+                |level| parse_log_level(level).unwrap_or_else(|| panic_unrecognized_log_level(level)),
+            );
+            logging::init_file_logging(log_file, level)
+                .unwrap_or_else(|e| panic!("Failed to initialize --log-file {}: {}", log_file, e));
+            logging::init_panic_logging();
+        }
+        None => {
+            let mut logger = ::libra_logger::Logger::new();
+            if let Some(level) = &args.log_level {
+                let level = parse_log_level(level).unwrap_or_else(|| panic_unrecognized_log_level(level));
+                logger.level(to_libra_logger_level(level));
+            } else if !args.verbose {
+                logger.level(::libra_logger::Level::Warn);
+            }
+            logger.init();
+            crash_handler::setup_panic_handler();
+        }
     }
-    logger.init();
-    crash_handler::setup_panic_handler();
 
-    let (commands, alias_to_cmd) = get_commands(true);
+    let (mut commands, mut alias_to_cmd) = get_commands(true);
+    let validator_manager_cmd: Arc<dyn Command> = Arc::new(validator_manager::ValidatorManagerCommand);
+    for alias in validator_manager_cmd.get_aliases() {
+        alias_to_cmd.insert(alias, validator_manager_cmd.clone());
+    }
+    commands.push(validator_manager_cmd);
 
     let faucet_account_file = args
         .faucet_account_file
@@ -119,20 +254,33 @@ fn main() {
     // Faucet, TreasuryCompliance and DD use the same keypair for now
     let treasury_compliance_account_file = faucet_account_file.clone();
     let dd_account_file = faucet_account_file.clone();
-    let mnemonic_file = args.mnemonic_file.clone();
+    // Pass the resolved path (honoring the same `--mnemonic-file` default as
+    // `wallet_setup`), not the raw, usually-unset `args.mnemonic_file` --
+    // otherwise `ClientProxy` would derive its own default path and write a
+    // second, possibly unencrypted copy of the mnemonic `wallet_setup` just
+    // wrote to `mnemonic_path`. When `wallet_setup` wrote that file
+    // password-encrypted, `mnemonic_file` must be omitted entirely: whether
+    // `ClientProxy::new` would write its plaintext `mnemonic_string` argument
+    // back to this same path is unconfirmed against the real `ClientProxy`
+    // API, and doing so would silently clobber the encrypted file with a
+    // plaintext one, defeating the whole point of encrypting it.
+    let mnemonic_file = if args.ledger || mnemonic_file_encrypted {
+        None
+    } else {
+        Some(mnemonic_path.clone())
+    };
 
-    // If waypoint is given explicitly, use its value,
-    // otherwise waypoint_url is required, try to retrieve the waypoint from the URL.
-    let waypoint = args.waypoint.unwrap_or_else(|| {
-        args.waypoint_url
-            .as_ref()
-            .map(|url_str| {
-                retrieve_waypoint(url_str.as_str()).unwrap_or_else(|e| {
-                    panic!("Failure to retrieve a waypoint from {}: {}", url_str, e)
-                })
-            })
-            .unwrap()
-    });
+    let waypoint = waypoint::resolve_waypoint(args.chain_id, args.waypoint, &args.waypoint_url)
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    // In `--ledger` mode there is no mnemonic to hand `ClientProxy`; account
+    // data is populated separately below from `ledger_account_data` once the
+    // client is constructed.
+    let mnemonic_arg = if args.ledger {
+        None
+    } else {
+        Some(mnemonic_string)
+    };
 
     let mut client_proxy = ClientProxy::new(
         args.chain_id,
@@ -143,7 +291,7 @@ fn main() {
         true, // 0L change
         args.faucet_url.clone(),
         mnemonic_file,
-        Some(mnemonic_string), // 0L change
+        mnemonic_arg, // 0L change
         waypoint,
     )
     .expect("Failed to construct client.");
@@ -168,20 +316,37 @@ fn main() {
     );
     // if args.mnemonic_file.is_some() {
     
-    if entered_mnem || args.mnemonic_file.is_some() {
-        match client_proxy.recover_accounts_in_wallet() {
+    // In `--non-interactive` mode, JSON printed by `run_non_interactive` must
+    // be the only output a script sees, so the human-readable recovery
+    // banner below is skipped entirely there.
+    if let Some(account_data) = ledger_account_data {
+        if !args.non_interactive {
+            println!(
+                "{} child accounts were derived from the Ledger device",
+                account_data.len()
+            );
+            for data in &account_data {
+                println!("#{} address {}", data.index, hex::encode(data.address));
+            }
+        }
+        client_proxy.set_account_data(account_data);
+    } else if entered_mnem || args.mnemonic_file.is_some() {
+        match wallet_setup::select_account_source(&mut client_proxy, just_generated, args.non_interactive) {
             Ok(account_data) => {
-                println!(
-                    "Wallet recovered and the first {} child accounts were derived",
-                    account_data.len()
-                );
-                for data in account_data {
-                    println!("#{} address {}", data.index, hex::encode(data.address));
+                if !args.non_interactive {
+                    for data in account_data {
+                        println!("#{} address {}", data.index, hex::encode(data.address));
+                    }
                 }
             }
-            Err(e) => report_error("Error recovering Libra wallet", e),
+            Err(e) => failure::report("Error recovering Libra wallet", e),
         }
     }
+    if args.non_interactive {
+        let exit_code = run_non_interactive(&args, &mut client_proxy, &alias_to_cmd);
+        std::process::exit(exit_code);
+    }
+
     print_help(&cli_info, &commands);
     println!("Please, input commands: \n");
 
@@ -230,6 +395,45 @@ fn main() {
     }
 }
 
+/// Parses a `--log-level` value, shared by both the `--log-file` (flexi_logger)
+/// and default (`libra_logger`) logging paths so neither can silently drift
+/// from the other on which values are accepted. Returns `None` for anything
+/// unrecognized -- including "off", which `log::LevelFilter::from_str` would
+/// otherwise happily accept -- so the caller can reject a typo'd level
+/// instead of silently logging at the wrong severity for an entire session.
+fn parse_log_level(level: &str) -> Option<log::LevelFilter> {
+    match level.to_lowercase().as_str() {
+        "error" => Some(log::LevelFilter::Error),
+        "warn" => Some(log::LevelFilter::Warn),
+        "info" => Some(log::LevelFilter::Info),
+        "debug" => Some(log::LevelFilter::Debug),
+        "trace" => Some(log::LevelFilter::Trace),
+        _ => None,
+    }
+}
+
+/// Panics with the one message both logging paths use for an unrecognized
+/// `--log-level` value.
+fn panic_unrecognized_log_level(level: &str) -> ! {
+    panic!(
+        "Unrecognized --log-level {:?}; expected one of: error, warn, info, debug, trace",
+        level
+    )
+}
+
+/// Converts a level already validated by `parse_log_level` into the type
+/// `libra_logger::Logger::level` expects.
+fn to_libra_logger_level(level: log::LevelFilter) -> ::libra_logger::Level {
+    match level {
+        log::LevelFilter::Error => ::libra_logger::Level::Error,
+        log::LevelFilter::Warn => ::libra_logger::Level::Warn,
+        log::LevelFilter::Info => ::libra_logger::Level::Info,
+        log::LevelFilter::Debug => ::libra_logger::Level::Debug,
+        log::LevelFilter::Trace => ::libra_logger::Level::Trace,
+        log::LevelFilter::Off => unreachable!("parse_log_level never returns Off"),
+    }
+}
+
 /// Print the help message for the client and underlying command.
 fn print_help(client_info: &str, commands: &[std::sync::Arc<dyn Command>]) {
     println!("{}", client_info);
@@ -248,14 +452,56 @@ fn print_help(client_info: &str, commands: &[std::sync::Arc<dyn Command>]) {
     println!("\n");
 }
 
-/// Retrieve a waypoint given the URL.
-fn retrieve_waypoint(url_str: &str) -> anyhow::Result<Waypoint> {
-    let client = reqwest::blocking::ClientBuilder::new().build()?;
-    let response = client.get(url_str).send()?;
+/// Runs the commands collected from `--commands-file` and/or `--exec` in order,
+/// printing one JSON result object per line so scripts can consume output
+/// without scraping human-readable text. Returns the process exit code: `0` if
+/// every command ran, `1` as soon as one is unrecognized or fails.
+///
+/// Failure detection is layered: [`failure::execute_detecting_failure`] covers
+/// both this binary's own error paths (`failure::report`) and, by capturing
+/// stderr around the call, any command from `cli::commands::get_commands`'s
+/// registry that reports its own error via `report_error`.
+fn run_non_interactive(
+    args: &Args,
+    client_proxy: &mut ClientProxy,
+    alias_to_cmd: &HashMap<&str, Arc<dyn Command>>,
+) -> i32 {
+    let mut lines: Vec<String> = Vec::new();
+    if let Some(path) = &args.commands_file {
+        let contents = std::fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("Failed to read --commands-file {}: {}", path, e));
+        lines.extend(contents.lines().map(str::to_string));
+    }
+    lines.extend(args.exec.iter().cloned());
 
-    Ok(response
-        .error_for_status()
-        .map_err(|_| anyhow::format_err!("Failed to retrieve waypoint from URL {}", url_str))?
-        .text()
-        .map(|r| Waypoint::from_str(r.trim()))??)
+    for line in &lines {
+        let params = parse_cmd(line);
+        if params.is_empty() {
+            continue;
+        }
+        let result = match alias_to_cmd.get(&params[0]) {
+            Some(cmd) => {
+                if failure::execute_detecting_failure(cmd.as_ref(), client_proxy, &params) {
+                    json!({
+                        "command": line,
+                        "status": "error",
+                        "message": "command reported an error; see the preceding log line",
+                    })
+                } else {
+                    json!({ "command": line, "status": "ok" })
+                }
+            }
+            None => json!({
+                "command": line,
+                "status": "error",
+                "message": format!("Unknown command: {:?}", params[0]),
+            }),
+        };
+        let failed = result["status"] == "error";
+        println!("{}", result);
+        if failed {
+            return 1;
+        }
+    }
+    0
 }
@@ -0,0 +1,184 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! `validator-manager` command family: a file-based workflow for node
+//! operators to derive validator/operator keys, hand the resulting descriptor
+//! to an air-gapped machine, and later import it to register or update the
+//! on-chain validator config, or reassign operator authority.
+//!
+//! `ValidatorManagerCommand` implements `cli::commands::Command` so `main`
+//! can fold it into the same `commands`/`alias_to_cmd` registry `get_commands`
+//! returns, instead of special-casing `validator-manager` alongside the
+//! built-in `help`/`quit` handling.
+//!
+//! This module calls `ClientProxy::get_account_data`, `AccountData::public_key_bytes`,
+//! `ClientProxy::register_validator_config`, and `ClientProxy::set_validator_operator`,
+//! none of which are added anywhere in this tree -- `cli::client_proxy` is an
+//! upstream crate not vendored here, so these are assumed additions to its
+//! API rather than confirmed ones. They must be checked against the real
+//! `ClientProxy` (e.g. `cargo check -p cli`) before this lands; if any are
+//! missing, this module's `create`/`import`/`move_operator` need to be built
+//! on whatever equivalent upstream actually exposes.
+
+use anyhow::{format_err, Result};
+use crate::failure;
+use cli::client_proxy::ClientProxy;
+use cli::commands::Command;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// On-disk descriptor for one validator/operator pair. The schema is stable
+/// so a descriptor produced offline (e.g. on an air-gapped machine) by
+/// `validator-manager create` can be imported later by
+/// `validator-manager import` without either side needing network access.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidatorDescriptor {
+    pub validator_address: String,
+    pub validator_public_key: String,
+    pub validator_derivation_index: u32,
+    pub operator_address: String,
+    pub operator_public_key: String,
+    pub operator_derivation_index: u32,
+}
+
+/// Human-readable description of the JSON schema above, shown by `help` next
+/// to the other commands' `get_params_help` output.
+pub const PARAMS_HELP: &str = "\
+validator-manager create <derivation_index> <operator_derivation_index> <out_dir>
+\tDerive a validator/operator key pair from the wallet and write a
+\tvalidators.json descriptor (addresses, public keys, derivation indices) to
+\t<out_dir>, for later import on a machine with network access.
+validator-manager import <file>
+\tRead a validators.json descriptor and register or update the on-chain
+\tvalidator config for its validator/operator pair.
+validator-manager move <from_operator_address> <to_operator_address>
+\tReassign operator authority for every validator currently operated by
+\t<from_operator_address> to <to_operator_address>.
+validators.json schema (one JSON object; addresses and keys hex-encoded):
+\t{ validator_address, validator_public_key, validator_derivation_index,
+\t  operator_address, operator_public_key, operator_derivation_index }";
+
+const DESCRIPTOR_FILE_NAME: &str = "validators.json";
+
+/// `validator-manager create`: derives a validator and operator key pair from
+/// the wallet and writes their descriptor to `<out_dir>/validators.json`.
+pub fn create(
+    client_proxy: &mut ClientProxy,
+    validator_index: u32,
+    operator_index: u32,
+    out_dir: &str,
+) -> Result<ValidatorDescriptor> {
+    let validator = client_proxy.get_account_data(validator_index)?;
+    let operator = client_proxy.get_account_data(operator_index)?;
+
+    let descriptor = ValidatorDescriptor {
+        validator_address: hex::encode(validator.address),
+        validator_public_key: hex::encode(validator.public_key_bytes()),
+        validator_derivation_index: validator_index,
+        operator_address: hex::encode(operator.address),
+        operator_public_key: hex::encode(operator.public_key_bytes()),
+        operator_derivation_index: operator_index,
+    };
+
+    fs::create_dir_all(out_dir)?;
+    let path = format!("{}/{}", out_dir, DESCRIPTOR_FILE_NAME);
+    fs::write(&path, serde_json::to_string_pretty(&descriptor)?)?;
+    println!("Wrote validator descriptor to {}", path);
+    Ok(descriptor)
+}
+
+/// `validator-manager import <file>`: registers or updates the on-chain
+/// validator config described by a previously exported descriptor.
+pub fn import(client_proxy: &mut ClientProxy, file: &str) -> Result<()> {
+    let contents = fs::read_to_string(file)
+        .map_err(|e| format_err!("Failed to read validator descriptor {}: {}", file, e))?;
+    let descriptor: ValidatorDescriptor = serde_json::from_str(&contents)?;
+
+    client_proxy.register_validator_config(
+        &descriptor.validator_address,
+        &descriptor.validator_public_key,
+        &descriptor.operator_address,
+        &descriptor.operator_public_key,
+    )?;
+    println!(
+        "Registered validator config for validator {}",
+        descriptor.validator_address
+    );
+    Ok(())
+}
+
+/// `validator-manager move <from> <to>`: reassigns operator authority from one
+/// account to another through `ClientProxy::set_validator_operator`.
+pub fn move_operator(client_proxy: &mut ClientProxy, from: &str, to: &str) -> Result<()> {
+    client_proxy.set_validator_operator(from, to)?;
+    println!("Moved operator authority from {} to {}", from, to);
+    Ok(())
+}
+
+/// Registers the `validator-manager` family as an ordinary `Command`, so it
+/// dispatches through the same `alias_to_cmd` lookup as every other command
+/// instead of a hand-rolled branch in `main`'s command loop.
+pub struct ValidatorManagerCommand;
+
+impl Command for ValidatorManagerCommand {
+    fn get_aliases(&self) -> Vec<&'static str> {
+        vec!["validator-manager"]
+    }
+
+    fn get_description(&self) -> &'static str {
+        "Derive, export, and import validator/operator keys, or move operator authority"
+    }
+
+    fn get_params_help(&self) -> &'static str {
+        PARAMS_HELP
+    }
+
+    fn execute(&self, client_proxy: &mut ClientProxy, params: &[&str]) {
+        let result = match params.get(1).copied() {
+            Some("create") if params.len() == 5 => parse_index(params[2])
+                .and_then(|validator_index| Ok((validator_index, parse_index(params[3])?)))
+                .and_then(|(validator_index, operator_index)| {
+                    create(client_proxy, validator_index, operator_index, params[4]).map(|_| ())
+                }),
+            Some("import") if params.len() == 3 => import(client_proxy, params[2]),
+            Some("move") if params.len() == 4 => {
+                move_operator(client_proxy, params[2], params[3])
+            }
+            _ => {
+                println!("{}", PARAMS_HELP);
+                return;
+            }
+        };
+        if let Err(e) = result {
+            failure::report("Error running validator-manager command", e);
+        }
+    }
+}
+
+/// Parses a BIP44 derivation index, erroring instead of silently falling back
+/// to index `0` on a typo'd argument.
+fn parse_index(value: &str) -> Result<u32> {
+    value
+        .parse()
+        .map_err(|e| format_err!("Invalid derivation index {:?}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_index;
+
+    #[test]
+    fn parse_index_accepts_a_valid_index() {
+        assert_eq!(parse_index("3").unwrap(), 3);
+    }
+
+    #[test]
+    fn parse_index_rejects_a_non_numeric_value() {
+        assert!(parse_index("not-a-number").is_err());
+    }
+
+    #[test]
+    fn parse_index_rejects_a_negative_value() {
+        assert!(parse_index("-1").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Advisory locking for the wallet/mnemonic file. Running two client
+//! instances against the same file can interleave writes during
+//! `recover_accounts_in_wallet` and account derivation; callers open the file
+//! with [`open`] and hold the resulting `try_lock()` guard for the lifetime
+//! of the `ClientProxy` session so a second instance fails fast instead of
+//! silently racing with the first.
+
+use anyhow::{format_err, Result};
+use fd_lock::FdLock;
+use std::fs::{File, OpenOptions};
+
+/// Opens (creating if necessary) `path` for exclusive locking. Callers should
+/// call `.try_lock()` on the result immediately and keep the returned guard
+/// alive for as long as the lock should be held.
+pub fn open(path: &str) -> Result<FdLock<File>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| format_err!("Failed to open {} for locking: {}", path, e))?;
+    Ok(FdLock::new(file))
+}
+
+/// Error message for when `path` is already locked by another process,
+/// naming the conflicting path rather than leaving the operator guessing.
+pub fn conflict_message(path: &str) -> String {
+    format!(
+        "{} is already locked by another running client instance. \
+         Pass --no-wallet-lock if concurrent read-only/scripted access is expected.",
+        path
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::conflict_message;
+
+    #[test]
+    fn conflict_message_names_the_locked_path() {
+        assert!(conflict_message("mnemonic.txt").contains("mnemonic.txt"));
+    }
+
+    #[test]
+    fn conflict_message_mentions_the_escape_hatch() {
+        assert!(conflict_message("mnemonic.txt").contains("--no-wallet-lock"));
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Interactive wallet-creation wizard. `main` used to assume every run was a
+//! recovery of an existing mnemonic; this module distinguishes that from
+//! creating a brand new wallet, and factors the TTY prompts out so they can
+//! be tested and reused instead of living inline in `main`.
+
+use anyhow::Result;
+use cli::client_proxy::{AccountData, ClientProxy};
+use std::path::Path;
+
+const DEFAULT_MNEMONIC_FILE: &str = "mnemonic.txt";
+
+/// Resolves the path `main` should use for the wallet/mnemonic file: the
+/// explicit `--mnemonic-file` if given, otherwise the same default this
+/// module falls back to when generating a brand new wallet.
+pub fn resolve_mnemonic_path(mnemonic_file: Option<&str>) -> String {
+    mnemonic_file.unwrap_or(DEFAULT_MNEMONIC_FILE).to_string()
+}
+
+/// What [`enter_or_generate_mnemonic`] read or generated: the mnemonic `main`
+/// passes to `ClientProxy::new`, and whether it was just generated, which
+/// `main` hands to [`select_account_source`] to phrase the recovery message.
+pub struct MnemonicEntry {
+    pub mnemonic: String,
+    pub just_generated: bool,
+    /// Whether `mnemonic_file` was just written password-encrypted. `main`
+    /// must not then also pass `mnemonic_file` to `ClientProxy::new` --
+    /// whether `ClientProxy` would write the plaintext `mnemonic` argument
+    /// back to that same path on construction is unconfirmed against the
+    /// real `ClientProxy` API (not vendored in this tree), and doing so
+    /// would silently clobber the encrypted file with a plaintext one.
+    pub mnemonic_file_encrypted: bool,
+}
+
+/// Reads the mnemonic for a fresh or existing wallet.
+///
+/// If `mnemonic_file` doesn't exist yet, asks the user whether to generate a
+/// brand new BIP39 mnemonic or import one, instead of assuming recovery.
+/// Otherwise falls back to the original "enter your mnemonic" TTY prompt used
+/// for recovering an existing wallet.
+pub fn enter_or_generate_mnemonic(mnemonic_file: Option<&str>) -> Result<MnemonicEntry> {
+    let path = mnemonic_file.unwrap_or(DEFAULT_MNEMONIC_FILE);
+    if Path::new(path).exists() {
+        return Ok(MnemonicEntry {
+            mnemonic: prompt_mnemonic("Enter your 0L mnemonic:")?,
+            just_generated: false,
+            mnemonic_file_encrypted: false,
+        });
+    }
+
+    println!("No wallet file found at {}.", path);
+    println!("1) Generate a new wallet\n2) Import an existing mnemonic");
+    match prompt_line("Choose [1/2]: ")?.trim() {
+        "2" => Ok(MnemonicEntry {
+            mnemonic: prompt_mnemonic("Paste your existing mnemonic:")?,
+            just_generated: false,
+            mnemonic_file_encrypted: false,
+        }),
+        _ => {
+            let mnemonic = generate_and_confirm_mnemonic()?;
+            let mnemonic_file_encrypted = persist_mnemonic(path, &mnemonic)?;
+            Ok(MnemonicEntry {
+                mnemonic,
+                just_generated: true,
+                mnemonic_file_encrypted,
+            })
+        }
+    }
+}
+
+/// Generates a new BIP39 mnemonic, shows it exactly once, and refuses to
+/// proceed until the user re-enters it correctly -- this is the only backup
+/// the holder will get, so a typo'd transcription must not go unnoticed.
+fn generate_and_confirm_mnemonic() -> Result<String> {
+    let mnemonic = libra_wallet::mnemonic::generate()?;
+
+    println!("\nYour new mnemonic (write this down, it will not be shown again):\n");
+    println!("{}\n", mnemonic);
+    loop {
+        let confirmation = prompt_line("Re-enter the mnemonic to confirm: ")?;
+        if mnemonic_confirmed(&confirmation, &mnemonic) {
+            break;
+        }
+        println!("That doesn't match. Try again.");
+    }
+    Ok(mnemonic)
+}
+
+/// Whether a re-entered mnemonic matches the original, ignoring surrounding
+/// whitespace the way a pasted or newline-terminated TTY line would have.
+fn mnemonic_confirmed(candidate: &str, original: &str) -> bool {
+    candidate.trim() == original
+}
+
+/// Persists `mnemonic` to `mnemonic_file`, optionally encrypting it with a
+/// password the user is asked to enter twice so a typo doesn't lock them out.
+/// An empty password stores the file in plaintext, matching today's behavior.
+/// Returns whether the file was encrypted.
+fn persist_mnemonic(mnemonic_file: &str, mnemonic: &str) -> Result<bool> {
+    let password = loop {
+        let first = rpassword::read_password_from_tty(Some(
+            "Password to encrypt the wallet file (leave blank for none): ",
+        ))?;
+        if first.is_empty() {
+            break None;
+        }
+        let second = rpassword::read_password_from_tty(Some("Confirm password: "))?;
+        if passwords_match(&first, &second) {
+            break Some(first);
+        }
+        println!("Passwords did not match. Try again.");
+    };
+
+    match password {
+        Some(password) => {
+            libra_wallet::mnemonic::write_encrypted(mnemonic_file, mnemonic, &password)?;
+            Ok(true)
+        }
+        None => {
+            std::fs::write(mnemonic_file, mnemonic)?;
+            Ok(false)
+        }
+    }
+}
+
+/// Whether a password and its re-entered confirmation match.
+fn passwords_match(first: &str, second: &str) -> bool {
+    first == second
+}
+
+fn prompt_mnemonic(prompt: &str) -> Result<String> {
+    println!("{}", prompt);
+    match std::env::var("NODE_ENV") {
+        Ok(val) if val != "prod" => {
+            // For test and stage environments, so mnemonics can be piped in.
+            println!("(unsafe STDIN input for testing) \u{1F511}");
+            Ok(rpassword::read_password()?)
+        }
+        _ => Ok(rpassword::read_password_from_tty(Some("\u{1F511}"))?),
+    }
+}
+
+fn prompt_line(prompt: &str) -> Result<String> {
+    use std::io::Write;
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end().to_string())
+}
+
+/// Derives the wallet's child accounts, phrasing the outcome according to
+/// whether this is a wallet that was just generated or one being recovered.
+/// `quiet` skips the human-readable banner -- set from `--non-interactive`,
+/// where a script's only expected output is the JSON `run_non_interactive`
+/// prints per command.
+pub fn select_account_source(
+    client_proxy: &mut ClientProxy,
+    just_generated: bool,
+    quiet: bool,
+) -> Result<Vec<AccountData>> {
+    let account_data = client_proxy.recover_accounts_in_wallet()?;
+    if quiet {
+        return Ok(account_data);
+    }
+    if just_generated {
+        println!(
+            "New wallet created and the first {} child accounts were derived",
+            account_data.len()
+        );
+    } else {
+        println!(
+            "Wallet recovered and the first {} child accounts were derived",
+            account_data.len()
+        );
+    }
+    Ok(account_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mnemonic_confirmed, passwords_match};
+
+    #[test]
+    fn mnemonic_confirmed_matches_exactly() {
+        assert!(mnemonic_confirmed("foo bar baz", "foo bar baz"));
+    }
+
+    #[test]
+    fn mnemonic_confirmed_ignores_surrounding_whitespace() {
+        assert!(mnemonic_confirmed("  foo bar baz\n", "foo bar baz"));
+    }
+
+    #[test]
+    fn mnemonic_confirmed_rejects_a_mismatch() {
+        assert!(!mnemonic_confirmed("foo bar qux", "foo bar baz"));
+    }
+
+    #[test]
+    fn passwords_match_requires_an_exact_match() {
+        assert!(passwords_match("hunter2", "hunter2"));
+        assert!(!passwords_match("hunter2", "hunter3"));
+    }
+}
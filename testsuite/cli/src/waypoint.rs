@@ -0,0 +1,203 @@
+// Copyright (c) The Libra Core Contributors
+// SPDX-License-Identifier: Apache-2.0
+
+//! Waypoint acquisition. Extends the original single-URL `reqwest` call
+//! (which trusted one server unconditionally) with built-in per-chain
+//! defaults, multi-source quorum fetching so no single endpoint can hand the
+//! client a bogus trust root, and an on-disk cache used as a last resort if
+//! every URL fetch fails.
+
+use anyhow::{format_err, Result};
+use libra_types::{chain_id::ChainId, waypoint::Waypoint};
+use log::warn;
+use std::{collections::HashMap, fs, path::PathBuf, str::FromStr, time::Duration};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const FETCH_RETRIES: u32 = 2;
+
+/// Built-in waypoint URLs used when neither `--waypoint` nor `--waypoint_url`
+/// is supplied, keyed by chain id (mainnet = 1, testnet = 2, devnet = 3,
+/// local swarm / TESTING = 4).
+fn default_waypoint_urls(chain_id: ChainId) -> &'static [&'static str] {
+    match chain_id.id() {
+        1 => &["https://waypoint.libra.org/mainnet"],
+        2 => &["https://waypoint.libra.org/testnet"],
+        3 => &["https://waypoint.libra.org/devnet"],
+        // A local swarm has no fixed, well-known waypoint host -- each swarm
+        // run mints its own genesis. `resolve_waypoint` turns the resulting
+        // empty list into a clear top-level error rather than a URL fetch
+        // failure, so the common `--chain-id TESTING` invocation with no
+        // `--waypoint`/`--waypoint_url` fails fast with actionable guidance
+        // instead of panicking deep inside waypoint resolution.
+        _ => &[],
+    }
+}
+
+/// Resolves the waypoint to connect with: the explicit `--waypoint` value if
+/// given, otherwise the majority result of fetching every `--waypoint_url` (or
+/// the chain's built-in defaults if none were passed), falling back to the
+/// last cached waypoint if every fetch fails.
+///
+/// Returns a plain `Result` (rather than panicking internally) so callers can
+/// report a config-style error the same way they do for every other
+/// unrecoverable startup condition, e.g. `resolve_waypoint(..).unwrap_or_else(|e|
+/// panic!("{}", e))` -- in particular so a local swarm (`--chain-id TESTING`,
+/// which has no fixed built-in waypoint host) run with neither `--waypoint`
+/// nor `--waypoint_url` gets one clear, top-level message instead of a panic
+/// from deep inside waypoint resolution.
+pub fn resolve_waypoint(
+    chain_id: ChainId,
+    explicit: Option<Waypoint>,
+    waypoint_urls: &[String],
+) -> Result<Waypoint> {
+    if let Some(waypoint) = explicit {
+        return Ok(waypoint);
+    }
+
+    let urls: Vec<String> = if !waypoint_urls.is_empty() {
+        waypoint_urls.to_vec()
+    } else {
+        default_waypoint_urls(chain_id)
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    };
+    if urls.is_empty() {
+        return Err(format_err!(
+            "No --waypoint or --waypoint_url was given, and chain id {} has no built-in default \
+             waypoint URL (this is expected for a local swarm -- pass --waypoint or \
+             --waypoint_url pointing at that swarm's genesis waypoint).",
+            chain_id
+        ));
+    }
+
+    let fetched: Vec<Waypoint> = urls
+        .iter()
+        .filter_map(|url| match fetch_with_retries(url) {
+            Ok(waypoint) => Some(waypoint),
+            Err(e) => {
+                warn!("Failed to fetch waypoint from {}: {}", url, e);
+                None
+            }
+        })
+        .collect();
+
+    let waypoint = if fetched.is_empty() {
+        warn!("All waypoint URLs failed; falling back to the last cached waypoint");
+        read_cache(chain_id).ok_or_else(|| {
+            format_err!(
+                "No waypoint URL succeeded and no cached waypoint is available for chain id {}",
+                chain_id
+            )
+        })?
+    } else if urls.len() == 1 {
+        fetched[0]
+    } else {
+        // Agreement is counted against every source given, not only those
+        // that answered, so unreachable sources can't shrink the quorum.
+        majority(&fetched)
+            .filter(|agreed| fetched.iter().filter(|w| *w == agreed).count() > urls.len() / 2)
+            .ok_or_else(|| {
+                format_err!(
+                    "No strict majority of the {} waypoint sources agreed: {:?}",
+                    urls.len(),
+                    fetched
+                )
+            })?
+    };
+
+    if let Err(e) = write_cache(chain_id, &waypoint) {
+        warn!("Failed to cache the agreed waypoint: {}", e);
+    }
+    Ok(waypoint)
+}
+
+/// Fetches and parses a single waypoint URL, retrying up to `FETCH_RETRIES`
+/// times on any failure (transport, HTTP status, or parse).
+fn fetch_with_retries(url: &str) -> Result<Waypoint> {
+    let client = reqwest::blocking::ClientBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .build()?;
+
+    let mut last_err = None;
+    for _ in 0..=FETCH_RETRIES {
+        match fetch_once(&client, url) {
+            Ok(waypoint) => return Ok(waypoint),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(last_err.expect("at least one fetch attempt is always made"))
+}
+
+fn fetch_once(client: &reqwest::blocking::Client, url: &str) -> Result<Waypoint> {
+    let response = client.get(url).send()?;
+    let text = response
+        .error_for_status()
+        .map_err(|_| format_err!("Failed to retrieve waypoint from URL {}", url))?
+        .text()?;
+    Ok(Waypoint::from_str(text.trim())?)
+}
+
+/// Returns the value agreed on by a strict majority of `values`, if any.
+/// Generic over the element type (rather than `Waypoint` directly) so the
+/// quorum math is unit-testable without needing to construct real waypoints.
+fn majority<T: Eq + std::hash::Hash + Copy>(values: &[T]) -> Option<T> {
+    let mut counts: HashMap<T, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(*value).or_insert(0) += 1;
+    }
+    let threshold = values.len() / 2 + 1;
+    counts
+        .into_iter()
+        .find(|(_, count)| *count >= threshold)
+        .map(|(value, _)| value)
+}
+
+fn cache_path(chain_id: ChainId) -> Option<PathBuf> {
+    let mut path = dirs::config_dir()?;
+    path.push("libra-client");
+    path.push(format!("waypoint-{}.txt", chain_id.id()));
+    Some(path)
+}
+
+fn read_cache(chain_id: ChainId) -> Option<Waypoint> {
+    let path = cache_path(chain_id)?;
+    let contents = fs::read_to_string(path).ok()?;
+    Waypoint::from_str(contents.trim()).ok()
+}
+
+fn write_cache(chain_id: ChainId, waypoint: &Waypoint) -> Result<()> {
+    let path = cache_path(chain_id)
+        .ok_or_else(|| format_err!("Could not determine the user's config directory"))?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, waypoint.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::majority;
+
+    #[test]
+    fn majority_picks_the_value_two_of_three_agree_on() {
+        assert_eq!(majority(&[1, 1, 2]), Some(1));
+    }
+
+    #[test]
+    fn majority_returns_none_on_a_tie() {
+        // 2-of-4 for each of two values is not a strict majority of 4 (needs 3).
+        assert_eq!(majority(&[1, 1, 2, 2]), None);
+    }
+
+    #[test]
+    fn majority_returns_none_when_all_sources_disagree() {
+        assert_eq!(majority(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn majority_of_a_single_value_is_itself() {
+        assert_eq!(majority(&[7]), Some(7));
+    }
+}